@@ -0,0 +1,81 @@
+use argon2::{Argon2, PasswordHash, PasswordVerifier};
+use chrono::Utc;
+use tracing::warn;
+
+use crate::config::{ApiKeyConfig, Role};
+
+// 設定済みのAPIキー群の中に、提示された秘密鍵と一致しかつ有効期間内のものがあれば、
+// そのキーに設定されたroleを返す。`verify_password` 自体がArgon2内部で定数時間比較を
+// 行うため、ここで追加のタイミング対策は不要。
+pub fn verify(candidates: &[ApiKeyConfig], presented: &str) -> Option<Role> {
+    let now = Utc::now();
+
+    candidates.iter().find_map(|candidate| {
+        let hash = match PasswordHash::new(&candidate.hash) {
+            Ok(hash) => hash,
+            Err(e) => {
+                warn!("⚠️ 設定中のAPIキーハッシュが不正です: {}", e);
+                return None;
+            }
+        };
+
+        if Argon2::default()
+            .verify_password(presented.as_bytes(), &hash)
+            .is_err()
+        {
+            return None;
+        }
+
+        if candidate.valid_from.is_some_and(|from| now < from) {
+            return None;
+        }
+        if candidate.valid_until.is_some_and(|until| now > until) {
+            return None;
+        }
+
+        Some(candidate.role)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use argon2::password_hash::{rand_core::OsRng, SaltString};
+    use argon2::PasswordHasher;
+    use chrono::Duration;
+
+    fn hashed_key(secret: &str, valid_from: Option<chrono::DateTime<Utc>>, valid_until: Option<chrono::DateTime<Utc>>, role: Role) -> ApiKeyConfig {
+        let salt = SaltString::generate(&mut OsRng);
+        let hash = Argon2::default()
+            .hash_password(secret.as_bytes(), &salt)
+            .expect("ハッシュ生成に失敗")
+            .to_string();
+        ApiKeyConfig { hash, valid_from, valid_until, role }
+    }
+
+    #[test]
+    fn accepts_a_valid_key_within_its_validity_window() {
+        let candidates = vec![hashed_key("s3cret", None, None, Role::Operator)];
+        assert_eq!(verify(&candidates, "s3cret"), Some(Role::Operator));
+    }
+
+    #[test]
+    fn rejects_a_key_that_does_not_match_any_hash() {
+        let candidates = vec![hashed_key("s3cret", None, None, Role::Operator)];
+        assert_eq!(verify(&candidates, "wrong-secret"), None);
+    }
+
+    #[test]
+    fn rejects_a_key_before_its_valid_from() {
+        let now = Utc::now();
+        let candidates = vec![hashed_key("s3cret", Some(now + Duration::hours(1)), None, Role::Operator)];
+        assert_eq!(verify(&candidates, "s3cret"), None);
+    }
+
+    #[test]
+    fn rejects_a_key_after_its_valid_until() {
+        let now = Utc::now();
+        let candidates = vec![hashed_key("s3cret", None, Some(now - Duration::hours(1)), Role::Operator)];
+        assert_eq!(verify(&candidates, "s3cret"), None);
+    }
+}
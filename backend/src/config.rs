@@ -0,0 +1,108 @@
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use std::{fs, path::Path};
+
+// config.toml の内容をそのまま写した設定構造体。
+// 例:
+//   bind_port = 5000
+//
+//   [[api_key]]
+//   hash = "$argon2id$v=19$m=19456,t=2,p=1$..."
+//   valid_until = "2027-01-01T00:00:00Z"
+//
+//   [[zone]]
+//   name = "tomatoes"
+//   pin = 17
+//   default_duration_secs = 5
+#[derive(Deserialize, Clone)]
+pub struct Config {
+    #[serde(default = "default_bind_port")]
+    pub bind_port: u16,
+    #[serde(rename = "api_key")]
+    pub api_keys: Vec<ApiKeyConfig>,
+    #[serde(rename = "zone")]
+    pub zones: Vec<ZoneConfig>,
+}
+
+// 1枚のAPIキーの設定。平文は一切保存せず、Argon2のPHCハッシュ文字列と
+// 任意の有効期間 (valid_from/valid_until)、そしてそのキーが持つ権限 (role) を持つ
+#[derive(Deserialize, Clone)]
+pub struct ApiKeyConfig {
+    pub hash: String,
+    pub valid_from: Option<DateTime<Utc>>,
+    pub valid_until: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub role: Role,
+}
+
+// APIキーに紐づく権限。OperatorはViewer相当の操作もすべて行える上位権限とする
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    #[default]
+    Viewer,
+    Operator,
+}
+
+impl Role {
+    // このキーが `required` 権限を満たしているか (Operatorは常にViewerを満たす)
+    pub fn satisfies(self, required: Role) -> bool {
+        self == required || self == Role::Operator
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn operator_satisfies_any_requirement() {
+        assert!(Role::Operator.satisfies(Role::Operator));
+        assert!(Role::Operator.satisfies(Role::Viewer));
+    }
+
+    #[test]
+    fn viewer_only_satisfies_viewer() {
+        assert!(Role::Viewer.satisfies(Role::Viewer));
+        assert!(!Role::Viewer.satisfies(Role::Operator));
+    }
+}
+
+#[derive(Deserialize, Clone)]
+pub struct ZoneConfig {
+    pub name: String,
+    pub pin: u8,
+    pub default_duration_secs: u64,
+    // 要求された秒数に関わらず、ピンを連続してHIGHにできる最大秒数。
+    // ウォッチドッグはこれを超えた通電を検知すると強制的にLOWへ落とす
+    #[serde(default = "default_max_on_duration_secs")]
+    pub max_on_duration_secs: u64,
+}
+
+fn default_bind_port() -> u16 {
+    5000
+}
+
+fn default_max_on_duration_secs() -> u64 {
+    60
+}
+
+impl Config {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path)
+            .map_err(|e| format!("設定ファイル {} の読み込みに失敗: {}", path.display(), e))?;
+
+        let config: Config =
+            toml::from_str(&text).map_err(|e| format!("設定ファイルの解析に失敗: {}", e))?;
+
+        if config.zones.is_empty() {
+            return Err("設定ファイルに [[zone]] が1つも定義されていません".to_string());
+        }
+        if config.api_keys.is_empty() {
+            return Err("設定ファイルに [[api_key]] が1つも定義されていません".to_string());
+        }
+
+        Ok(config)
+    }
+}
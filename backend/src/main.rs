@@ -1,21 +1,36 @@
 use axum::{
-    extract::State,
+    extract::{Path, Query, State},
     http::{HeaderMap, StatusCode},
-    response::Json,
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        Json,
+    },
     routing::{get, post},
     Router,
 };
+use futures_util::Stream;
 use serde::{Deserialize, Serialize};
-use std::{net::SocketAddr, sync::Arc, time::Duration};
+use std::{
+    collections::HashMap,
+    convert::Infallible,
+    net::SocketAddr,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tokio_stream::StreamExt;
 use tower_http::cors::{Any, CorsLayer};
 use tracing::{info, warn};
+use uuid::Uuid;
 
-// ================================================================
-// 設定項目
-// ================================================================
-const API_SECRET_KEY: &str = "0228";
-const WATER_PUMP_PIN: u8 = 17;
-const WATER_DURATION_SECS: u64 = 5;
+mod auth;
+mod config;
+mod relay_client;
+mod watchdog;
+
+use config::{ApiKeyConfig, Config, Role};
+use watchdog::ZoneWatchdog;
 
 // ================================================================
 // GPIO制御の抽象化
@@ -35,9 +50,9 @@ impl GpioController {
         {
             match Gpio::new() {
                 Ok(gpio) => match gpio.get(pin) {
-                    Ok(pin) => {
-                        let output = pin.into_output_low();
-                        info!("✅ GPIO初期化成功: ピン {} を出力に設定", WATER_PUMP_PIN);
+                    Ok(pin_handle) => {
+                        let output = pin_handle.into_output_low();
+                        info!("✅ GPIO初期化成功: ピン {} を出力に設定", pin);
                         return Self::Real(output);
                     }
                     Err(e) => {
@@ -49,28 +64,26 @@ impl GpioController {
                 }
             }
         }
-        
-        info!("⚠️ ダミーモードで起動します");
+
+        info!("⚠️ ピン {} はダミーモードで起動します", pin);
         Self::Dummy
     }
 
-    async fn run_motor(&mut self, duration: Duration) -> Result<String, String> {
+    // 呼び出し側 (water_zone / シャットダウン処理 / ウォッチドッグ) が
+    // いつでもピンを操作できるよう、HIGH/LOWの切り替えだけを公開する
+    fn set_high(&mut self) {
         match self {
             #[cfg(feature = "gpio")]
-            Self::Real(pin) => {
-                info!("🚀 モーターON");
-                pin.set_high();
-                tokio::time::sleep(duration).await;
-                pin.set_low();
-                info!("🛑 モーターOFF");
-                Ok(format!("実機実行: {}秒間モーターを制御しました", duration.as_secs()))
-            }
-            Self::Dummy => {
-                info!("--- [DUMMY MODE] モーター動作シミュレーション: {}秒 ---", duration.as_secs());
-                tokio::time::sleep(duration).await;
-                info!("--- [DUMMY MODE] 動作完了 ---");
-                Ok(format!("ダミー実行: {}秒間モーターを制御しました", duration.as_secs()))
-            }
+            Self::Real(pin) => pin.set_high(),
+            Self::Dummy => {}
+        }
+    }
+
+    fn set_low(&mut self) {
+        match self {
+            #[cfg(feature = "gpio")]
+            Self::Real(pin) => pin.set_low(),
+            Self::Dummy => {}
         }
     }
 
@@ -79,13 +92,93 @@ impl GpioController {
     }
 }
 
+// 指定したゾーンのポンプを `duration` 秒間だけ動かし、進捗をprogressへ流す。
+// ピンがHIGHの間ずっとgpioのロックを握り続けることはしない (ウォッチドッグが
+// 水やり中でも強制消灯できるようにするため) - HIGH/LOWの瞬間だけロックする。
+// ウォッチドッグの `turned_on_at` は1ゾーンにつき1回の通電しか表現できないため、
+// 同一ゾーンへ同時に複数のリクエスト (通常POSTとSSEストリーム等) が来ても
+// 早期終了が割り込まないよう、実行全体を `watering_lock` で直列化する
+async fn water_zone(
+    zone: &ZoneState,
+    duration: Duration,
+    progress: &mpsc::Sender<WaterProgressEvent>,
+) -> Result<String, String> {
+    let _guard = zone.watering_lock.lock().await;
+
+    let _ = progress
+        .send(WaterProgressEvent::Started {
+            duration: duration.as_secs(),
+        })
+        .await;
+
+    let is_dummy = {
+        let mut gpio = zone.gpio.lock().await;
+        let is_dummy = gpio.is_dummy();
+        gpio.set_high();
+        is_dummy
+    };
+    zone.watchdog.mark_on();
+    info!(
+        "🚀 モーターON {}",
+        if is_dummy { "[DUMMY MODE]" } else { "" }
+    );
+
+    for elapsed in 1..=duration.as_secs() {
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        // ウォッチドッグが途中で強制消灯していたら、それ以上の待機は打ち切る
+        if !zone.watchdog.is_on() {
+            break;
+        }
+        let _ = progress.send(WaterProgressEvent::Running { elapsed }).await;
+    }
+
+    zone.gpio.lock().await.set_low();
+    zone.watchdog.mark_off();
+    info!("🛑 モーターOFF");
+    let _ = progress.send(WaterProgressEvent::Stopped).await;
+
+    Ok(format!(
+        "{}: {}秒間モーターを制御しました",
+        if is_dummy { "ダミー実行" } else { "実機実行" },
+        duration.as_secs()
+    ))
+}
+
 // ================================================================
 // アプリケーション状態
 // ================================================================
-struct AppState {
+// ゾーン1つ分の状態: 担当するGPIOコントローラー、省略時に使うデフォルト秒数、
+// そして最大通電時間を監視するウォッチドッグ
+struct ZoneState {
     gpio: tokio::sync::Mutex<GpioController>,
+    pin: u8,
+    default_duration_secs: u64,
+    max_on_duration_secs: u64,
+    watchdog: ZoneWatchdog,
+    // 同一ゾーンへの水やりリクエストを直列化し、並行実行がウォッチドッグの
+    // turned_on_at状態を奪い合って互いの通電を早期終了させないようにする
+    watering_lock: tokio::sync::Mutex<()>,
 }
 
+struct AppState {
+    api_keys: Vec<ApiKeyConfig>,
+    // ゾーン名 (config.tomlの [[zone]] name) → そのゾーンの状態
+    zones: HashMap<String, ZoneState>,
+    // 発行済みのストリームトークン (トークン文字列 → その権限と失効時刻)。
+    // SSE接続1回限りの使い捨てで、長期間有効なAPIキーをURL (アクセスログ/ブラウザ履歴に
+    // 残る) に載せずに済むようにするためのもの
+    stream_tokens: tokio::sync::Mutex<HashMap<String, StreamToken>>,
+}
+
+// /water/{zone}/stream-token が発行する使い捨てトークン1件分の状態
+struct StreamToken {
+    role: Role,
+    expires_at: Instant,
+}
+
+// ストリームトークンの有効期限。発行からこの秒数以内にSSE接続を開始しなければ失効する
+const STREAM_TOKEN_TTL_SECS: u64 = 30;
+
 // ================================================================
 // API型定義
 // ================================================================
@@ -94,12 +187,30 @@ struct StatusResponse {
     status: String,
     message: String,
     server_mode: String,
-    controlled_pin: u8,
+    zones: Vec<String>,
+    // いずれかのゾーンでウォッチドッグが発動したことがあれば、その最新時刻一覧
+    watchdog_trips: Vec<WatchdogTrip>,
 }
 
-#[derive(Deserialize)]
+#[derive(Serialize)]
+struct WatchdogTrip {
+    zone: String,
+    last_tripped_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Serialize)]
+struct ZoneSummary {
+    name: String,
+    pin: u8,
+    default_duration_secs: u64,
+    max_on_duration_secs: u64,
+    server_mode: String,
+    last_tripped_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(Deserialize, Default)]
 struct WaterRequest {
-    action: String,
+    duration_secs: Option<u64>,
 }
 
 #[derive(Serialize)]
@@ -114,13 +225,32 @@ struct ErrorResponse {
     error: String,
 }
 
+// 水やり進捗として /water/{zone}/stream から流れるSSEイベント
+#[derive(Serialize, Clone)]
+#[serde(tag = "phase", rename_all = "lowercase")]
+enum WaterProgressEvent {
+    Started { duration: u64 },
+    Running { elapsed: u64 },
+    Stopped,
+}
+
 // ================================================================
 // ミドルウェア: APIキー検証
 // ================================================================
-async fn validate_api_key(headers: &HeaderMap) -> Result<(), (StatusCode, Json<ErrorResponse>)> {
-    match headers.get("X-API-KEY") {
-        Some(key) if key == API_SECRET_KEY => Ok(()),
-        _ => Err((
+// 認証済みリクエストが1件持つコンテキスト。今のところ認証キーのroleのみを運ぶ
+struct RequestContext {
+    role: Role,
+}
+
+async fn validate_api_key(
+    headers: &HeaderMap,
+    api_keys: &[ApiKeyConfig],
+) -> Result<RequestContext, (StatusCode, Json<ErrorResponse>)> {
+    let presented = headers.get("X-API-KEY").and_then(|v| v.to_str().ok());
+
+    match presented.and_then(|key| auth::verify(api_keys, key)) {
+        Some(role) => Ok(RequestContext { role }),
+        None => Err((
             StatusCode::UNAUTHORIZED,
             Json(ErrorResponse {
                 error: "Unauthorized: Invalid API Key".to_string(),
@@ -129,6 +259,72 @@ async fn validate_api_key(headers: &HeaderMap) -> Result<(), (StatusCode, Json<E
     }
 }
 
+// EventSourceはカスタムヘッダーを送れないため、/water/{zone}/streamは長期間有効な
+// APIキーの代わりに発行済みのストリームトークンをクエリパラメータで受け取る。
+// トークンは /water/{zone}/stream-token で都度発行され、1回の検証で消費されて
+// 失効するため、URLに載っても (アクセスログやブラウザ履歴に残っても) 使い回せない
+async fn validate_stream_token(
+    tokens: &tokio::sync::Mutex<HashMap<String, StreamToken>>,
+    presented: Option<&str>,
+) -> Result<RequestContext, (StatusCode, Json<ErrorResponse>)> {
+    let unauthorized = || {
+        Err((
+            StatusCode::UNAUTHORIZED,
+            Json(ErrorResponse {
+                error: "Unauthorized: Invalid or expired stream token".to_string(),
+            }),
+        ))
+    };
+
+    let Some(token) = presented else {
+        return unauthorized();
+    };
+
+    let mut tokens = tokens.lock().await;
+    match tokens.remove(token) {
+        Some(entry) if entry.expires_at >= Instant::now() => Ok(RequestContext { role: entry.role }),
+        _ => unauthorized(),
+    }
+}
+
+// `ctx` の役割が `required` を満たさなければ、既存の401とは区別される403を返す
+fn require_role(
+    ctx: &RequestContext,
+    required: Role,
+) -> Result<(), (StatusCode, Json<ErrorResponse>)> {
+    if ctx.role.satisfies(required) {
+        Ok(())
+    } else {
+        Err((
+            StatusCode::FORBIDDEN,
+            Json(ErrorResponse {
+                error: format!("Forbidden: この操作には {:?} 権限が必要です", required),
+            }),
+        ))
+    }
+}
+
+#[derive(Deserialize)]
+struct StreamAuthParams {
+    token: Option<String>,
+}
+
+#[derive(Serialize)]
+struct StreamTokenResponse {
+    token: String,
+    expires_in_secs: u64,
+}
+
+// 設定されたゾーン名と一致しない場合に返す404
+fn zone_not_found(zone: &str) -> (StatusCode, Json<ErrorResponse>) {
+    (
+        StatusCode::NOT_FOUND,
+        Json(ErrorResponse {
+            error: format!("ゾーン '{}' は設定されていません", zone),
+        }),
+    )
+}
+
 // ================================================================
 // APIハンドラー
 // ================================================================
@@ -136,44 +332,90 @@ async fn status_handler(
     State(state): State<Arc<AppState>>,
     headers: HeaderMap,
 ) -> Result<Json<StatusResponse>, (StatusCode, Json<ErrorResponse>)> {
-    validate_api_key(&headers).await?;
+    let ctx = validate_api_key(&headers, &state.api_keys).await?;
+    require_role(&ctx, Role::Viewer)?;
 
-    let gpio = state.gpio.lock().await;
-    let mode = if gpio.is_dummy() {
-        "DUMMY MODE"
-    } else {
-        "LIVE RPi.GPIO MODE"
-    };
+    // すべてのゾーンがダミーモードなら DUMMY MODE、1つでも実機があれば LIVE とみなす
+    let mut all_dummy = true;
+    for zone in state.zones.values() {
+        if !zone.gpio.lock().await.is_dummy() {
+            all_dummy = false;
+            break;
+        }
+    }
+    let mode = if all_dummy { "DUMMY MODE" } else { "LIVE RPi.GPIO MODE" };
+
+    let watchdog_trips = state
+        .zones
+        .iter()
+        .filter_map(|(name, zone)| {
+            zone.watchdog.last_tripped_at().map(|last_tripped_at| WatchdogTrip {
+                zone: name.clone(),
+                last_tripped_at,
+            })
+        })
+        .collect();
 
     Ok(Json(StatusResponse {
         status: "Ready".to_string(),
         message: "Server is ready".to_string(),
         server_mode: mode.to_string(),
-        controlled_pin: WATER_PUMP_PIN,
+        zones: state.zones.keys().cloned().collect(),
+        watchdog_trips,
     }))
 }
 
+async fn zones_handler(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<ZoneSummary>>, (StatusCode, Json<ErrorResponse>)> {
+    let ctx = validate_api_key(&headers, &state.api_keys).await?;
+    require_role(&ctx, Role::Viewer)?;
+
+    let mut zones = Vec::with_capacity(state.zones.len());
+    for (name, zone) in &state.zones {
+        let gpio = zone.gpio.lock().await;
+        zones.push(ZoneSummary {
+            name: name.clone(),
+            pin: zone.pin,
+            default_duration_secs: zone.default_duration_secs,
+            max_on_duration_secs: zone.max_on_duration_secs,
+            server_mode: if gpio.is_dummy() {
+                "DUMMY MODE".to_string()
+            } else {
+                "LIVE RPi.GPIO MODE".to_string()
+            },
+            last_tripped_at: zone.watchdog.last_tripped_at(),
+        });
+    }
+    zones.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Ok(Json(zones))
+}
+
 async fn water_handler(
     State(state): State<Arc<AppState>>,
+    Path(zone_name): Path<String>,
     headers: HeaderMap,
-    Json(payload): Json<WaterRequest>,
+    body: Option<Json<WaterRequest>>,
 ) -> Result<Json<WaterResponse>, (StatusCode, Json<ErrorResponse>)> {
-    validate_api_key(&headers).await?;
+    let ctx = validate_api_key(&headers, &state.api_keys).await?;
+    require_role(&ctx, Role::Operator)?;
 
-    if payload.action != "start" {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse {
-                error: "Invalid request body. Expected {'action': 'start'}".to_string(),
-            }),
-        ));
-    }
+    let zone = state.zones.get(&zone_name).ok_or_else(|| zone_not_found(&zone_name))?;
+    let duration_secs = body
+        .and_then(|Json(payload)| payload.duration_secs)
+        .unwrap_or(zone.default_duration_secs);
+
+    info!("[リクエスト受信] 水やり: ゾーン '{}' ({}秒)", zone_name, duration_secs);
 
-    info!("[リクエスト受信] 水やり ({}秒)", WATER_DURATION_SECS);
+    // 一括応答のみが欲しいこの経路では、進捗イベントは読み捨てる。
+    // 受信側を誰も読まないとチャンネルが満杯になりwater_zone側のsendが
+    // ブロックし続けてしまうため、バックグラウンドで黙って drain する
+    let (progress_tx, mut progress_rx) = mpsc::channel(8);
+    tokio::spawn(async move { while progress_rx.recv().await.is_some() {} });
 
-    let mut gpio = state.gpio.lock().await;
-    let result = gpio
-        .run_motor(Duration::from_secs(WATER_DURATION_SECS))
+    let result = water_zone(zone, Duration::from_secs(duration_secs), &progress_tx)
         .await
         .map_err(|e| {
             (
@@ -186,16 +428,83 @@ async fn water_handler(
 
     Ok(Json(WaterResponse {
         status: "success".to_string(),
-        message: format!("水やり ({}秒) が完了しました", WATER_DURATION_SECS),
+        message: format!("ゾーン '{}' の水やり ({}秒) が完了しました", zone_name, duration_secs),
         gpio_result: result,
     }))
 }
 
+// /water/{zone}/stream で使う使い捨てトークンを1枚発行する。APIキー自体は
+// 通常どおりヘッダーでのみ受け取るため、長期間有効な秘密鍵がURLに載ることはない
+async fn stream_token_handler(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Result<Json<StreamTokenResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let ctx = validate_api_key(&headers, &state.api_keys).await?;
+    require_role(&ctx, Role::Operator)?;
+
+    let token = Uuid::new_v4().to_string();
+    state.stream_tokens.lock().await.insert(
+        token.clone(),
+        StreamToken {
+            role: ctx.role,
+            expires_at: Instant::now() + Duration::from_secs(STREAM_TOKEN_TTL_SECS),
+        },
+    );
+
+    Ok(Json(StreamTokenResponse {
+        token,
+        expires_in_secs: STREAM_TOKEN_TTL_SECS,
+    }))
+}
+
+// 水やりの進捗をSSEでリアルタイム配信するハンドラー
+async fn water_stream_handler(
+    State(state): State<Arc<AppState>>,
+    Path(zone_name): Path<String>,
+    Query(auth): Query<StreamAuthParams>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, (StatusCode, Json<ErrorResponse>)> {
+    let ctx = validate_stream_token(&state.stream_tokens, auth.token.as_deref()).await?;
+    require_role(&ctx, Role::Operator)?;
+
+    if !state.zones.contains_key(&zone_name) {
+        return Err(zone_not_found(&zone_name));
+    }
+
+    let (progress_tx, progress_rx) = mpsc::channel::<WaterProgressEvent>(16);
+
+    tokio::spawn(async move {
+        let zone = &state.zones[&zone_name];
+        info!(
+            "[リクエスト受信] 水やり(ストリーミング): ゾーン '{}' ({}秒)",
+            zone_name, zone.default_duration_secs
+        );
+        if let Err(e) =
+            water_zone(zone, Duration::from_secs(zone.default_duration_secs), &progress_tx).await
+        {
+            warn!("GPIO操作エラー: {}", e);
+        }
+    });
+
+    let stream = ReceiverStream::new(progress_rx).map(|event| {
+        Ok(Event::default()
+            .json_data(&event)
+            .expect("WaterProgressEventのシリアライズに失敗"))
+    });
+
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+}
+
 // ================================================================
 // メイン関数
 // ================================================================
 #[tokio::main]
 async fn main() {
+    // `watering-backend gen-key` はサーバーを起動せず、標準入力から読んだ秘密鍵の
+    // Argon2ハッシュを1行だけ出力する。config.tomlの [[api_key]] にそのまま貼り付ける
+    if std::env::args().nth(1).as_deref() == Some("gen-key") {
+        return gen_key();
+    }
+
     // ログ初期化
     tracing_subscriber::fmt()
         .with_target(false)
@@ -203,12 +512,54 @@ async fn main() {
         .init();
 
     info!("--- Rust Axum APIサーバー起動 ---");
-    info!("制御ピン (BCM): {}", WATER_PUMP_PIN);
 
-    // GPIO初期化
-    let gpio = GpioController::new(WATER_PUMP_PIN);
+    let config_path = std::env::var("CONFIG_PATH").unwrap_or_else(|_| "config.toml".to_string());
+    let config = Config::load(&config_path).unwrap_or_else(|e| {
+        panic!("設定ファイル '{}' の読み込みに失敗しました: {}", config_path, e)
+    });
+
+    info!("設定ファイル: {} ({}ゾーン)", config_path, config.zones.len());
+
+    let zones = config
+        .zones
+        .iter()
+        .map(|zone| {
+            info!("ゾーン '{}' 制御ピン (BCM): {}", zone.name, zone.pin);
+            (
+                zone.name.clone(),
+                ZoneState {
+                    gpio: tokio::sync::Mutex::new(GpioController::new(zone.pin)),
+                    pin: zone.pin,
+                    default_duration_secs: zone.default_duration_secs,
+                    max_on_duration_secs: zone.max_on_duration_secs,
+                    watchdog: ZoneWatchdog::new(zone.max_on_duration_secs),
+                    watering_lock: tokio::sync::Mutex::new(()),
+                },
+            )
+        })
+        .collect();
+
     let app_state = Arc::new(AppState {
-        gpio: tokio::sync::Mutex::new(gpio),
+        api_keys: config.api_keys.clone(),
+        zones,
+        stream_tokens: tokio::sync::Mutex::new(HashMap::new()),
+    });
+
+    // Ctrl-C / SIGTERM を受けたら watch チャンネルで全体に通知し、ウォッチドッグ
+    // タスクを止めたうえで全ゾーンのピンを強制的にLOWへ落としてから終了する
+    let (shutdown_tx, shutdown_rx) = tokio::sync::watch::channel(false);
+    tokio::spawn(watchdog::run(app_state.clone(), shutdown_rx.clone()));
+
+    let shutdown_state = app_state.clone();
+    let mut shutdown_signal_rx = shutdown_rx.clone();
+    tokio::spawn(async move {
+        wait_for_shutdown_signal().await;
+        info!("シャットダウン要求を受信しました。全ゾーンのピンをLOWにします");
+        let _ = shutdown_tx.send(true);
+        for zone in shutdown_state.zones.values() {
+            zone.gpio.lock().await.set_low();
+            zone.watchdog.mark_off();
+        }
     });
 
     // CORSの設定
@@ -220,14 +571,111 @@ async fn main() {
     // ルーター構築
     let app = Router::new()
         .route("/status", get(status_handler))
-        .route("/water", post(water_handler))
+        .route("/zones", get(zones_handler))
+        .route("/water/{zone}", post(water_handler))
+        .route("/water/{zone}/stream-token", post(stream_token_handler))
+        .route("/water/{zone}/stream", get(water_stream_handler))
         .layer(cors)
         .with_state(app_state);
 
-    // サーバー起動
-    let addr = SocketAddr::from(([0, 0, 0, 0], 5000));
-    info!("🚀 サーバー起動: http://{}", addr);
+    // RELAY_URL / DEVICE_ID が設定されていれば、インバウンドポートを一切開けずに
+    // リレー経由のoutbound-onlyモードで起動する (Tailscale等が使えない環境向け)
+    match (std::env::var("RELAY_URL"), std::env::var("DEVICE_ID")) {
+        (Ok(relay_url), Ok(device_id)) => {
+            info!(
+                "🌐 リレー経由モードで起動します: {} (device_id={})",
+                relay_url, device_id
+            );
+            relay_client::run(&relay_url, &device_id, app, shutdown_signal_rx).await;
+        }
+        _ => {
+            let addr = SocketAddr::from(([0, 0, 0, 0], config.bind_port));
+            info!("🚀 サーバー起動: http://{}", addr);
+
+            let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
+            axum::serve(listener, app)
+                .with_graceful_shutdown(async move {
+                    let _ = shutdown_signal_rx.wait_for(|&stopped| stopped).await;
+                })
+                .await
+                .unwrap();
+        }
+    }
+}
+
+// Ctrl-C (SIGINT) または SIGTERM のどちらかを受信するまで待機する
+async fn wait_for_shutdown_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("Ctrl-Cハンドラーの設定に失敗しました");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("SIGTERMハンドラーの設定に失敗しました")
+            .recv()
+            .await;
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+}
+
+// 標準入力から秘密鍵を1行読み取り、Argon2のPHCハッシュ文字列を標準出力へ印字する
+fn gen_key() {
+    use argon2::password_hash::{rand_core::OsRng, PasswordHasher, SaltString};
+    use argon2::Argon2;
+    use std::io::BufRead;
+
+    let mut secret = String::new();
+    std::io::stdin()
+        .lock()
+        .read_line(&mut secret)
+        .expect("標準入力の読み込みに失敗しました");
+    let secret = secret.trim();
+
+    let salt = SaltString::generate(&mut OsRng);
+    let hash = Argon2::default()
+        .hash_password(secret.as_bytes(), &salt)
+        .expect("ハッシュ生成に失敗しました")
+        .to_string();
 
-    let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
-    axum::serve(listener, app).await.unwrap();
+    println!("{}", hash);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn viewer_satisfies_viewer_but_not_operator() {
+        let ctx = RequestContext { role: Role::Viewer };
+        assert!(require_role(&ctx, Role::Viewer).is_ok());
+        assert!(require_role(&ctx, Role::Operator).is_err());
+    }
+
+    #[test]
+    fn operator_satisfies_both_roles() {
+        let ctx = RequestContext { role: Role::Operator };
+        assert!(require_role(&ctx, Role::Viewer).is_ok());
+        assert!(require_role(&ctx, Role::Operator).is_ok());
+    }
+
+    // Viewerキーで/water相当の操作を叩いたときは、鍵自体が不正な401ではなく
+    // 権限不足を表す403で拒否されることを確認する
+    #[test]
+    fn under_privileged_role_is_rejected_with_403_not_401() {
+        let ctx = RequestContext { role: Role::Viewer };
+        let Err((status, _)) = require_role(&ctx, Role::Operator) else {
+            panic!("Viewerロールでのrequire_role(Operator)は失敗するはず");
+        };
+        assert_eq!(status, StatusCode::FORBIDDEN);
+    }
 }
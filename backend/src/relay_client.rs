@@ -0,0 +1,146 @@
+use axum::{body::Body, http::Request, Router};
+use futures_util::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tokio_tungstenite::tungstenite::Message;
+use tower::ServiceExt;
+use tracing::{info, warn};
+
+// ================================================================
+// ワイヤーフォーマット - relay/src/main.rs と対になる
+// ================================================================
+#[derive(Serialize, Deserialize)]
+struct RelayedRequest {
+    request_id: String,
+    method: String,
+    uri: String,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct RelayedResponse {
+    request_id: String,
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+const RECONNECT_DELAY_SECS: u64 = 5;
+
+// リレーへ常設の外向き接続を張り、転送されてきたリクエストをそのままローカルの
+// Routerへディスパッチして応答を送り返す。接続が切れたら一定間隔で再接続を試みる。
+// `shutdown_rx` がtrueになったら、接続中/再接続待ち中のどちらであっても
+// その場で抜けてmainに制御を返す (直結axum::serve側のgraceful shutdownと対になる)
+pub async fn run(
+    relay_url: &str,
+    device_id: &str,
+    app: Router,
+    mut shutdown_rx: tokio::sync::watch::Receiver<bool>,
+) {
+    let ws_url = format!("{}/relay/{}/connect", relay_url, device_id);
+
+    while !*shutdown_rx.borrow() {
+        tokio::select! {
+            conn = tokio_tungstenite::connect_async(&ws_url) => {
+                match conn {
+                    Ok((stream, _)) => {
+                        info!("✅ リレーに接続しました: {}", ws_url);
+                        tokio::select! {
+                            result = handle_connection(stream, app.clone()) => {
+                                if let Err(e) = result {
+                                    warn!("⚠️ リレー接続が切断されました: {}", e);
+                                }
+                            }
+                            _ = shutdown_rx.changed() => {
+                                info!("シャットダウン要求を受信したため、リレー接続を終了します");
+                                return;
+                            }
+                        }
+                    }
+                    Err(e) => {
+                        warn!("⚠️ リレーへの接続に失敗: {}", e);
+                    }
+                }
+            }
+            _ = shutdown_rx.changed() => return,
+        }
+
+        info!("{}秒後に再接続します...", RECONNECT_DELAY_SECS);
+        tokio::select! {
+            _ = tokio::time::sleep(Duration::from_secs(RECONNECT_DELAY_SECS)) => {}
+            _ = shutdown_rx.changed() => return,
+        }
+    }
+}
+
+async fn handle_connection(
+    stream: tokio_tungstenite::WebSocketStream<
+        tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>,
+    >,
+    app: Router,
+) -> Result<(), String> {
+    let (mut ws_tx, mut ws_rx) = stream.split();
+
+    while let Some(msg) = ws_rx.next().await {
+        let msg = msg.map_err(|e| e.to_string())?;
+        let Message::Text(text) = msg else {
+            continue;
+        };
+
+        let relayed: RelayedRequest =
+            serde_json::from_str(&text).map_err(|e| format!("リクエスト解析エラー: {}", e))?;
+
+        let response = dispatch(&app, relayed).await;
+        let payload = serde_json::to_string(&response).map_err(|e| e.to_string())?;
+        ws_tx
+            .send(Message::Text(payload.into()))
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+
+    Ok(())
+}
+
+// 転送されてきたリクエストをローカルのRouterへ直接ディスパッチし、応答を組み立てる
+//
+// 注意: レスポンスボディを`to_bytes`で丸ごと読み切ってから1つのRelayedResponseとして
+// 返すため、/water/{zone}/streamのSSEはここを通るとストリーミングにならない。
+// `handle_connection`がWebSocket接続1本でリクエストを直列に処理する都合もあり、
+// 水やりが終わるまでそのリクエストが他のリクエストの処理をブロックしたうえで、
+// 全イベントが最後にまとめて届く。つまりリレー経由ではSSEは実質的に未対応であり、
+// ライブ進捗が必要な場合はPi側に直接到達できる経路 (direct/Tailscale等) を使うこと
+async fn dispatch(app: &Router, relayed: RelayedRequest) -> RelayedResponse {
+    let mut builder = Request::builder()
+        .method(relayed.method.as_str())
+        .uri(relayed.uri.as_str());
+    for (key, value) in &relayed.headers {
+        builder = builder.header(key, value);
+    }
+    let request = builder
+        .body(Body::from(relayed.body))
+        .expect("リクエストの再構築に失敗");
+
+    let response = app
+        .clone()
+        .oneshot(request)
+        .await
+        .expect("Routerの呼び出しに失敗");
+
+    let status = response.status().as_u16();
+    let headers = response
+        .headers()
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_str().unwrap_or_default().to_string()))
+        .collect();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap_or_default();
+
+    RelayedResponse {
+        request_id: relayed.request_id,
+        status,
+        headers,
+        body: body.to_vec(),
+    }
+}
@@ -0,0 +1,81 @@
+use chrono::{DateTime, Utc};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+use crate::AppState;
+
+// 1ゾーン分のウォッチドッグ状態。GpioControllerのMutexとは別に持つことで、
+// gpioロックを取らずに、水やり中でも現在の通電時間を監視できるようにする
+pub struct ZoneWatchdog {
+    max_on: Duration,
+    turned_on_at: Mutex<Option<Instant>>,
+    last_tripped_at: Mutex<Option<DateTime<Utc>>>,
+}
+
+impl ZoneWatchdog {
+    pub fn new(max_on_duration_secs: u64) -> Self {
+        Self {
+            max_on: Duration::from_secs(max_on_duration_secs),
+            turned_on_at: Mutex::new(None),
+            last_tripped_at: Mutex::new(None),
+        }
+    }
+
+    pub fn mark_on(&self) {
+        *self.turned_on_at.lock().unwrap() = Some(Instant::now());
+    }
+
+    pub fn mark_off(&self) {
+        *self.turned_on_at.lock().unwrap() = None;
+    }
+
+    pub fn is_on(&self) -> bool {
+        self.turned_on_at.lock().unwrap().is_some()
+    }
+
+    fn is_overdue(&self) -> bool {
+        self.turned_on_at
+            .lock()
+            .unwrap()
+            .is_some_and(|since| since.elapsed() > self.max_on)
+    }
+
+    pub fn last_tripped_at(&self) -> Option<DateTime<Utc>> {
+        *self.last_tripped_at.lock().unwrap()
+    }
+
+    fn record_trip(&self) {
+        *self.last_tripped_at.lock().unwrap() = Some(Utc::now());
+    }
+}
+
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+// すべてのゾーンを定期的に見回り、最大通電時間 (max_on_duration_secs) を
+// 超えているピンを強制的にLOWへ落とすバックグラウンドタスク
+pub async fn run(state: std::sync::Arc<AppState>, mut shutdown: tokio::sync::watch::Receiver<bool>) {
+    let mut interval = tokio::time::interval(POLL_INTERVAL);
+
+    loop {
+        tokio::select! {
+            _ = interval.tick() => {}
+            _ = shutdown.changed() => break,
+        }
+
+        for (name, zone) in &state.zones {
+            if !zone.watchdog.is_overdue() {
+                continue;
+            }
+
+            warn!(
+                "⚠️ ウォッチドッグ発動: ゾーン '{}' が最大通電時間 ({}秒) を超過したため強制的にLOWにします",
+                name,
+                zone.watchdog.max_on.as_secs()
+            );
+            zone.gpio.lock().await.set_low();
+            zone.watchdog.mark_off();
+            zone.watchdog.record_trip();
+        }
+    }
+}
@@ -0,0 +1,194 @@
+use axum::{
+    body::Body,
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Path, State,
+    },
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{any, get},
+    Router,
+};
+use dashmap::DashMap;
+use futures_util::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::{net::SocketAddr, sync::Arc, time::Duration};
+use tokio::sync::{mpsc, oneshot};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+// ================================================================
+// ワイヤーフォーマット - backend/src/relay_client.rs と対になる
+// ================================================================
+#[derive(Serialize, Deserialize)]
+struct RelayedRequest {
+    request_id: String,
+    method: String,
+    uri: String,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct RelayedResponse {
+    request_id: String,
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+type DeviceId = String;
+
+// ランデブー中のデバイス1台分の状態: リクエストを流す経路と、応答待ちのoneshot一覧
+struct DeviceConnection {
+    to_device: mpsc::Sender<RelayedRequest>,
+    pending: Arc<DashMap<String, oneshot::Sender<RelayedResponse>>>,
+}
+
+struct AppState {
+    devices: DashMap<DeviceId, DeviceConnection>,
+}
+
+const RESPONSE_TIMEOUT_SECS: u64 = 15;
+
+#[tokio::main]
+async fn main() {
+    tracing_subscriber::fmt()
+        .with_target(false)
+        .compact()
+        .init();
+
+    let state = Arc::new(AppState {
+        devices: DashMap::new(),
+    });
+
+    let app = Router::new()
+        .route("/relay/{device_id}/connect", get(connect_handler))
+        .route("/relay/{device_id}/{*path}", any(forward_handler))
+        .with_state(state);
+
+    let addr = SocketAddr::from(([0, 0, 0, 0], 6000));
+    info!("🔌 リレーサーバー起動: http://{}", addr);
+
+    let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
+    axum::serve(listener, app).await.unwrap();
+}
+
+// Piがここへ常設のWebSocket接続を張り、自身のdevice_idで登録する
+async fn connect_handler(
+    Path(device_id): Path<DeviceId>,
+    State(state): State<Arc<AppState>>,
+    ws: WebSocketUpgrade,
+) -> impl IntoResponse {
+    ws.on_upgrade(move |socket| handle_device_socket(device_id, state, socket))
+}
+
+async fn handle_device_socket(device_id: DeviceId, state: Arc<AppState>, socket: WebSocket) {
+    let (mut ws_tx, mut ws_rx) = socket.split();
+    let (to_device_tx, mut to_device_rx) = mpsc::channel::<RelayedRequest>(32);
+    let pending = Arc::new(DashMap::new());
+
+    state.devices.insert(
+        device_id.clone(),
+        DeviceConnection {
+            to_device: to_device_tx,
+            pending: pending.clone(),
+        },
+    );
+    info!("✅ デバイス接続: {}", device_id);
+
+    let forward_device_id = device_id.clone();
+    let forward_task = tokio::spawn(async move {
+        while let Some(req) = to_device_rx.recv().await {
+            let payload = serde_json::to_string(&req).expect("RelayedRequestのシリアライズ失敗");
+            if ws_tx.send(Message::Text(payload.into())).await.is_err() {
+                warn!("⚠️ デバイスへの転送に失敗: {}", forward_device_id);
+                break;
+            }
+        }
+    });
+
+    while let Some(Ok(msg)) = ws_rx.next().await {
+        if let Message::Text(text) = msg {
+            match serde_json::from_str::<RelayedResponse>(&text) {
+                Ok(resp) => {
+                    if let Some((_, sender)) = pending.remove(&resp.request_id) {
+                        let _ = sender.send(resp);
+                    }
+                }
+                Err(e) => warn!("⚠️ デバイスからの応答の解析に失敗: {}", e),
+            }
+        }
+    }
+
+    info!("🔌 デバイス切断: {}", device_id);
+    state.devices.remove(&device_id);
+    forward_task.abort();
+}
+
+// 公開クライアントからの /relay/{device_id}/{path} を待機中のPiへ中継する
+async fn forward_handler(
+    Path((device_id, path)): Path<(DeviceId, String)>,
+    State(state): State<Arc<AppState>>,
+    request: axum::extract::Request,
+) -> Response {
+    let Some(device) = state.devices.get(&device_id) else {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            format!("デバイス '{}' は現在接続されていません", device_id),
+        )
+            .into_response();
+    };
+
+    let request_id = Uuid::new_v4().to_string();
+    let (parts, body) = request.into_parts();
+    let body_bytes = axum::body::to_bytes(body, usize::MAX)
+        .await
+        .unwrap_or_default();
+    let headers = parts
+        .headers
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_str().unwrap_or_default().to_string()))
+        .collect();
+
+    let uri = match parts.uri.query() {
+        Some(query) => format!("/{}?{}", path, query),
+        None => format!("/{}", path),
+    };
+
+    let relayed = RelayedRequest {
+        request_id: request_id.clone(),
+        method: parts.method.to_string(),
+        uri,
+        headers,
+        body: body_bytes.to_vec(),
+    };
+
+    let (resp_tx, resp_rx) = oneshot::channel();
+    device.pending.insert(request_id.clone(), resp_tx);
+
+    if device.to_device.send(relayed).await.is_err() {
+        device.pending.remove(&request_id);
+        return (StatusCode::SERVICE_UNAVAILABLE, "デバイスとの接続が切断されました")
+            .into_response();
+    }
+
+    match tokio::time::timeout(Duration::from_secs(RESPONSE_TIMEOUT_SECS), resp_rx).await {
+        Ok(Ok(resp)) => {
+            let mut builder = axum::http::Response::builder().status(resp.status);
+            for (k, v) in resp.headers {
+                builder = builder.header(k, v);
+            }
+            builder.body(Body::from(resp.body)).unwrap()
+        }
+        Ok(Err(_)) => {
+            device.pending.remove(&request_id);
+            (StatusCode::BAD_GATEWAY, "デバイスとの接続が失われました").into_response()
+        }
+        Err(_) => {
+            device.pending.remove(&request_id);
+            (StatusCode::GATEWAY_TIMEOUT, "デバイスからの応答がタイムアウトしました")
+                .into_response()
+        }
+    }
+}
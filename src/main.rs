@@ -1,12 +1,37 @@
 use gloo_net::http::Request;
 use serde::{Deserialize, Serialize};
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::JsCast;
 use yew::prelude::*;
 
 // ================================================================
 // 設定項目 - ⚠️ ここをあなたのRaspberry PiのIPアドレスに変更してください
 // ================================================================
-const API_BASE_URL: &str = "/api";  // ← Raspberry PiのTailscale IP
-const API_KEY: &str = "0228";
+const API_BASE_URL: &str = "/api";  // ← Raspberry PiのTailscale IP、またはリレーの "https://relay.example.com/relay/{device_id}"
+
+// リレー経由で接続する場合は/water/{zone}/streamのSSEが使えない
+// (relay/src/main.rsが単一のリクエスト/レスポンスとしてしか中継できず、
+// イベントは水やり終了後にまとめて届き、リレーの応答タイムアウトを超える水やりでは
+// 失敗する) ため、このフラグをtrueにすると
+// 進捗表示なしの通常POST /water/{zone}にフォールバックする。API_BASE_URLを
+// リレーのURLに向けるときは併せてこれもtrueにすること
+const USE_RELAY: bool = false;
+
+// APIキーはビルドに埋め込まず、ブラウザのlocalStorageに保存したものを使う
+const API_KEY_STORAGE_KEY: &str = "watering-api-key";
+
+fn load_api_key() -> String {
+    web_sys::window()
+        .and_then(|w| w.local_storage().ok().flatten())
+        .and_then(|storage| storage.get_item(API_KEY_STORAGE_KEY).ok().flatten())
+        .unwrap_or_default()
+}
+
+fn save_api_key(key: &str) {
+    if let Some(storage) = web_sys::window().and_then(|w| w.local_storage().ok().flatten()) {
+        let _ = storage.set_item(API_KEY_STORAGE_KEY, key);
+    }
+}
 
 // ================================================================
 // API型定義
@@ -16,12 +41,24 @@ struct StatusResponse {
     status: String,
     message: String,
     server_mode: String,
-    controlled_pin: u8,
+    zones: Vec<String>,
+    watchdog_trips: Vec<WatchdogTrip>,
+}
+
+#[derive(Deserialize, Clone, PartialEq)]
+struct WatchdogTrip {
+    zone: String,
+    last_tripped_at: String,
 }
 
-#[derive(Serialize)]
-struct WaterRequest {
-    action: String,
+#[derive(Deserialize, Clone, PartialEq)]
+struct ZoneSummary {
+    name: String,
+    pin: u8,
+    default_duration_secs: u64,
+    max_on_duration_secs: u64,
+    server_mode: String,
+    last_tripped_at: Option<String>,
 }
 
 #[derive(Deserialize, Clone)]
@@ -31,6 +68,23 @@ struct WaterResponse {
     gpio_result: String,
 }
 
+// /water/{zone}/stream-token が発行する使い捨てトークン (バックエンドのStreamTokenResponseに対応)
+#[derive(Deserialize, Clone)]
+struct StreamTokenResponse {
+    token: String,
+    #[allow(dead_code)]
+    expires_in_secs: u64,
+}
+
+// /water/{zone}/stream がSSEで流す進捗イベント (バックエンドのWaterProgressEventに対応)
+#[derive(Deserialize, Clone, PartialEq)]
+#[serde(tag = "phase", rename_all = "lowercase")]
+enum WaterProgressEvent {
+    Started { duration: u64 },
+    Running { elapsed: u64 },
+    Stopped,
+}
+
 // ================================================================
 // アプリケーション状態
 // ================================================================
@@ -58,19 +112,35 @@ fn app() -> Html {
     let status = use_state(|| "待機中".to_string());
     let is_loading = use_state(|| false);
     let error = use_state(|| None::<String>);
+    // 水やり進捗 (経過秒, 合計秒) - /water/{zone}/stream のSSEイベントで更新される
+    let progress = use_state(|| None::<(u64, u64)>);
+    // GET /zones から取得したゾーン一覧と、現在選択中のゾーン
+    let zones = use_state(Vec::<ZoneSummary>::new);
+    let selected_zone = use_state(|| None::<String>);
+    let api_key = use_state(load_api_key);
+    // GET /status から取得したウォッチドッグ発動履歴 (フェイルセーフが作動したことがあるか)
+    let watchdog_trips = use_state(Vec::<WatchdogTrip>::new);
 
-    // サーバー状態確認
+    // サーバー状態確認 + ゾーン一覧取得
     let check_server_status = {
         let connection_type = connection_type.clone();
         let status = status.clone();
         let is_loading = is_loading.clone();
         let error = error.clone();
+        let zones = zones.clone();
+        let selected_zone = selected_zone.clone();
+        let api_key = api_key.clone();
+        let watchdog_trips = watchdog_trips.clone();
 
         Callback::from(move |_: web_sys::MouseEvent| {
             let connection_type = connection_type.clone();
             let status = status.clone();
             let is_loading = is_loading.clone();
             let error = error.clone();
+            let zones = zones.clone();
+            let selected_zone = selected_zone.clone();
+            let api_key = api_key.clone();
+            let watchdog_trips = watchdog_trips.clone();
 
             wasm_bindgen_futures::spawn_local(async move {
                 error.set(None);
@@ -79,7 +149,7 @@ fn app() -> Html {
                 connection_type.set(ConnectionType::None);
 
                 match Request::get(&format!("{}/status", API_BASE_URL))
-                    .header("X-API-KEY", API_KEY)
+                    .header("X-API-KEY", &api_key)
                     .send()
                     .await
                 {
@@ -88,6 +158,7 @@ fn app() -> Html {
                             Ok(data) => {
                                 status.set(format!("✅ 接続完了: {}", data.message));
                                 connection_type.set(ConnectionType::Remote);
+                                watchdog_trips.set(data.watchdog_trips);
                             }
                             Err(e) => {
                                 error.set(Some(format!("レスポンス解析エラー: {}", e)));
@@ -108,68 +179,207 @@ fn app() -> Html {
                     }
                 }
 
+                match Request::get(&format!("{}/zones", API_BASE_URL))
+                    .header("X-API-KEY", &api_key)
+                    .send()
+                    .await
+                {
+                    Ok(response) if response.ok() => {
+                        if let Ok(data) = response.json::<Vec<ZoneSummary>>().await {
+                            if selected_zone.is_none() {
+                                selected_zone.set(data.first().map(|z| z.name.clone()));
+                            }
+                            zones.set(data);
+                        }
+                    }
+                    _ => {
+                        zones.set(Vec::new());
+                    }
+                }
+
                 is_loading.set(false);
             });
         })
     };
 
-    // 水やりリクエスト
+    // 水やりリクエスト - 通常は/water/{zone}/streamをSSEで購読し、進捗をリアルタイムに反映する。
+    // リレー経由 (USE_RELAY=true) のときはSSEが使えないため、進捗表示なしの
+    // 通常POST /water/{zone}にフォールバックする
     let handle_watering = {
         let status = status.clone();
         let is_loading = is_loading.clone();
         let error = error.clone();
+        let progress = progress.clone();
+        let selected_zone = selected_zone.clone();
+        let api_key = api_key.clone();
 
         Callback::from(move |_: web_sys::MouseEvent| {
             let status = status.clone();
             let is_loading = is_loading.clone();
             let error = error.clone();
+            let progress = progress.clone();
+            let api_key = api_key.clone();
 
-            wasm_bindgen_futures::spawn_local(async move {
-                is_loading.set(true);
-                error.set(None);
-                status.set("水やり開始をリクエスト中...".to_string());
+            let Some(zone) = (*selected_zone).clone() else {
+                error.set(Some("ゾーンが選択されていません".to_string()));
+                return;
+            };
 
-                let request_body = WaterRequest {
-                    action: "start".to_string(),
-                };
+            is_loading.set(true);
+            error.set(None);
+            progress.set(None);
+            status.set(format!("水やり開始をリクエスト中... (ゾーン: {})", zone));
 
-                match Request::post(&format!("{}/water", API_BASE_URL))
-                    .header("X-API-KEY", API_KEY)
-                    .header("Content-Type", "application/json")
-                    .json(&request_body)
-                {
-                    Ok(request) => match request.send().await {
-                        Ok(response) if response.ok() => {
-                            match response.json::<WaterResponse>().await {
-                                Ok(result) => {
-                                    status.set(format!("✅ 成功: {}", result.message));
-                                }
-                                Err(e) => {
-                                    error.set(Some(format!("レスポンス解析エラー: {}", e)));
-                                    status.set("エラー".to_string());
-                                }
+            if USE_RELAY {
+                wasm_bindgen_futures::spawn_local(async move {
+                    match Request::post(&format!("{}/water/{}", API_BASE_URL, zone))
+                        .header("X-API-KEY", &api_key)
+                        .json(&serde_json::json!({}))
+                        .expect("水やりリクエストのシリアライズに失敗")
+                        .send()
+                        .await
+                    {
+                        Ok(response) if response.ok() => match response.json::<WaterResponse>().await {
+                            Ok(data) => {
+                                status.set(format!("✅ {}", data.message));
                             }
-                        }
-                        Ok(response) if response.status() == 401 => {
-                            error.set(Some("認証失敗: APIキーが間違っています".to_string()));
-                            status.set("認証失敗".to_string());
-                        }
+                            Err(e) => {
+                                error.set(Some(format!("レスポンス解析エラー: {}", e)));
+                                status.set("エラー".to_string());
+                            }
+                        },
                         Ok(response) => {
-                            error.set(Some(format!("サーバーエラー: {}", response.status())));
-                            status.set("通信失敗".to_string());
+                            error.set(Some(format!(
+                                "水やりに失敗しました (ステータス: {})",
+                                response.status()
+                            )));
+                            status.set("エラー".to_string());
                         }
                         Err(e) => {
-                            error.set(Some(format!("通信失敗: {}", e)));
-                            status.set("通信失敗".to_string());
+                            error.set(Some(format!("接続失敗: {}", e)));
+                            status.set("エラー".to_string());
+                        }
+                    }
+                    is_loading.set(false);
+                });
+                return;
+            }
+
+            // SSEのURLにAPIキーを直接載せると、長期間有効な秘密鍵がサーバー/リレーの
+            // アクセスログやブラウザ履歴に残ってしまう (chunk0-4が取り除いたのと同じ
+            // 漏洩経路)。そのため接続ごとに使い捨てのストリームトークンを先に発行させ、
+            // URLにはそれだけを載せる
+            wasm_bindgen_futures::spawn_local(async move {
+                let token = match Request::post(&format!(
+                    "{}/water/{}/stream-token",
+                    API_BASE_URL,
+                    js_sys::encode_uri_component(&zone)
+                ))
+                .header("X-API-KEY", &api_key)
+                .send()
+                .await
+                {
+                    Ok(response) if response.ok() => match response.json::<StreamTokenResponse>().await {
+                        Ok(data) => data.token,
+                        Err(e) => {
+                            error.set(Some(format!("ストリームトークンの解析エラー: {}", e)));
+                            status.set("エラー".to_string());
+                            is_loading.set(false);
+                            return;
                         }
                     },
+                    Ok(response) => {
+                        error.set(Some(format!(
+                            "ストリームトークンの取得に失敗しました (ステータス: {})",
+                            response.status()
+                        )));
+                        status.set("エラー".to_string());
+                        is_loading.set(false);
+                        return;
+                    }
                     Err(e) => {
-                        error.set(Some(format!("リクエスト作成エラー: {}", e)));
+                        error.set(Some(format!("接続失敗: {}", e)));
                         status.set("エラー".to_string());
+                        is_loading.set(false);
+                        return;
                     }
-                }
+                };
 
-                is_loading.set(false);
+                let url = format!(
+                    "{}/water/{}/stream?token={}",
+                    API_BASE_URL,
+                    js_sys::encode_uri_component(&zone),
+                    js_sys::encode_uri_component(&token)
+                );
+                let source = match web_sys::EventSource::new(&url) {
+                    Ok(source) => source,
+                    Err(e) => {
+                        error.set(Some(format!("EventSource作成エラー: {:?}", e)));
+                        status.set("エラー".to_string());
+                        is_loading.set(false);
+                        return;
+                    }
+                };
+
+                // Stateハンドルはクロージャ作成時点のスナップショットを返すため、
+                // Startedで受け取った総秒数はStateではなくこのセルに持たせて
+                // 後続のRunningから読み戻す
+                let total_duration = std::rc::Rc::new(std::cell::Cell::new(0u64));
+
+                let onmessage = Closure::<dyn FnMut(web_sys::MessageEvent)>::new({
+                    let source = source.clone();
+                    let status = status.clone();
+                    let is_loading = is_loading.clone();
+                    let error = error.clone();
+                    let progress = progress.clone();
+                    let total_duration = total_duration.clone();
+
+                    move |event: web_sys::MessageEvent| {
+                        let Some(data) = event.data().as_string() else {
+                            return;
+                        };
+                        match serde_json::from_str::<WaterProgressEvent>(&data) {
+                            Ok(WaterProgressEvent::Started { duration }) => {
+                                total_duration.set(duration);
+                                progress.set(Some((0, duration)));
+                                status.set(format!("💧 モーターON ({}秒間)", duration));
+                            }
+                            Ok(WaterProgressEvent::Running { elapsed }) => {
+                                let duration = total_duration.get();
+                                progress.set(Some((elapsed, duration)));
+                                status.set(format!("💧 水やり中... ({}/{}秒)", elapsed, duration));
+                            }
+                            Ok(WaterProgressEvent::Stopped) => {
+                                status.set("✅ 水やりが完了しました".to_string());
+                                is_loading.set(false);
+                                source.close();
+                            }
+                            Err(e) => {
+                                error.set(Some(format!("進捗イベント解析エラー: {}", e)));
+                            }
+                        }
+                    }
+                });
+
+                let onerror = Closure::<dyn FnMut(web_sys::Event)>::new({
+                    let source = source.clone();
+                    let status = status.clone();
+                    let is_loading = is_loading.clone();
+                    let error = error.clone();
+
+                    move |_: web_sys::Event| {
+                        error.set(Some("接続失敗: ストリームへの接続に失敗しました".to_string()));
+                        status.set("通信失敗".to_string());
+                        is_loading.set(false);
+                        source.close();
+                    }
+                });
+
+                source.set_onmessage(Some(onmessage.as_ref().unchecked_ref()));
+                source.set_onerror(Some(onerror.as_ref().unchecked_ref()));
+                // EventSourceが生きている間クロージャも保持する必要があるためリークする
+                onmessage.forget();
+                onerror.forget();
             });
         })
     };
@@ -199,7 +409,28 @@ fn app() -> Html {
     };
 
     // ボタンの有効/無効状態
-    let is_water_disabled = *is_loading || *connection_type == ConnectionType::None;
+    let is_water_disabled =
+        *is_loading || *connection_type == ConnectionType::None || selected_zone.is_none();
+
+    // ゾーン選択プルダウンの変更ハンドラー
+    let on_zone_change = {
+        let selected_zone = selected_zone.clone();
+        Callback::from(move |e: Event| {
+            let select: web_sys::HtmlSelectElement = e.target_unchecked_into();
+            selected_zone.set(Some(select.value()));
+        })
+    };
+
+    // APIキー入力欄の変更ハンドラー - localStorageに保存するだけで、ビルドには埋め込まない
+    let on_api_key_change = {
+        let api_key = api_key.clone();
+        Callback::from(move |e: Event| {
+            let input: web_sys::HtmlInputElement = e.target_unchecked_into();
+            let value = input.value();
+            save_api_key(&value);
+            api_key.set(value);
+        })
+    };
 
     html! {
         <div class="min-h-screen bg-gray-100 flex items-center justify-center p-4">
@@ -214,6 +445,18 @@ fn app() -> Html {
                     {"Tailscale経由でRaspberry Piに接続します"}
                 </p>
 
+                // APIキー入力 (ビルドに埋め込まず、ブラウザのlocalStorageにのみ保存する)
+                <div class="mb-6">
+                    <label class="block text-xs font-semibold text-gray-500 mb-1">{"APIキー"}</label>
+                    <input
+                        type="password"
+                        value={(*api_key).clone()}
+                        onchange={on_api_key_change}
+                        placeholder="APIキーを入力"
+                        class="w-full border border-gray-300 rounded-lg p-2 text-gray-700"
+                    />
+                </div>
+
                 // ステータスカード
                 <div class={format!("p-4 rounded-lg text-white mb-6 transition-colors duration-300 {}", status_color)}>
                     <div class="flex items-center justify-between">
@@ -232,6 +475,56 @@ fn app() -> Html {
                     </p>
                 </div>
 
+                // ゾーン選択
+                if !zones.is_empty() {
+                    <div class="mb-6">
+                        <label class="block text-xs font-semibold text-gray-500 mb-1">{"ゾーン"}</label>
+                        <select
+                            onchange={on_zone_change}
+                            disabled={*is_loading}
+                            class="w-full border border-gray-300 rounded-lg p-2 text-gray-700"
+                        >
+                            { for zones.iter().map(|zone| html! {
+                                <option
+                                    value={zone.name.clone()}
+                                    selected={Some(&zone.name) == selected_zone.as_ref()}
+                                >
+                                    {format!(
+                                        "{} (ピン{}, {}秒, 最大{}秒)",
+                                        zone.name, zone.pin, zone.default_duration_secs, zone.max_on_duration_secs
+                                    )}
+                                </option>
+                            }) }
+                        </select>
+                    </div>
+                }
+
+                // 進捗バー (進捗イベントの受信後に表示され、次の水やり開始まで最後の値のまま残る)
+                if let Some((elapsed, duration)) = &*progress {
+                    <div class="mb-6">
+                        <div class="w-full bg-gray-200 rounded-full h-3 overflow-hidden">
+                            <div
+                                class="bg-blue-500 h-3 rounded-full transition-all duration-500"
+                                style={format!(
+                                    "width: {}%",
+                                    (*elapsed as f64 / (*duration).max(1) as f64 * 100.0).min(100.0)
+                                )}
+                            />
+                        </div>
+                        <p class="text-xs text-gray-500 mt-1 text-right">{format!("{}/{}秒", elapsed, duration)}</p>
+                    </div>
+                }
+
+                // ウォッチドッグ発動履歴 (フェイルセーフが作動したことがあれば警告表示)
+                if !watchdog_trips.is_empty() {
+                    <div class="bg-amber-100 border-l-4 border-amber-500 text-amber-800 p-3 mb-6 rounded-md">
+                        <p class="font-bold">{"⚠️ ウォッチドッグ発動履歴"}</p>
+                        { for watchdog_trips.iter().map(|trip| html! {
+                            <p class="text-xs">{format!("{}: {}", trip.zone, trip.last_tripped_at)}</p>
+                        }) }
+                    </div>
+                }
+
                 // エラー表示
                 if let Some(err) = &*error {
                     <div class="bg-red-100 border-l-4 border-red-500 text-red-700 p-3 mb-6 rounded-md">